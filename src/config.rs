@@ -0,0 +1,87 @@
+use serde::Deserialize;
+
+/// `[vagrant]` table in `config.toml`.
+#[derive(Deserialize, Default, Debug)]
+pub struct VagrantConfig {
+    directories: Option<Vec<String>>,
+    power_on: Option<bool>,
+}
+
+/// `[containers]` table in `config.toml`.
+#[derive(Deserialize, Default, Debug)]
+pub struct ContainersConfig {
+    names: Option<Vec<String>>,
+    start: Option<bool>,
+    engine: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+struct ConfigFile {
+    vagrant: Option<VagrantConfig>,
+    containers: Option<ContainersConfig>,
+    disable: Option<Vec<String>>,
+    only: Option<Vec<String>>,
+}
+
+/// Merged view of `config.toml` and the flags the outer Topgrade invocation
+/// was started with.
+pub struct Config {
+    config_file: ConfigFile,
+    yes: bool,
+    dry_run: bool,
+    cleanup: bool,
+    verbose: bool,
+}
+
+impl Config {
+    pub fn yes(&self) -> bool {
+        self.yes
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn cleanup(&self) -> bool {
+        self.cleanup
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    pub fn disable(&self) -> &[String] {
+        self.config_file.disable.as_deref().unwrap_or_default()
+    }
+
+    pub fn only(&self) -> &[String] {
+        self.config_file.only.as_deref().unwrap_or_default()
+    }
+
+    pub fn vagrant_directories(&self) -> Option<&Vec<String>> {
+        self.config_file.vagrant.as_ref()?.directories.as_ref()
+    }
+
+    pub fn vagrant_power_on(&self) -> Option<bool> {
+        self.config_file.vagrant.as_ref()?.power_on
+    }
+
+    /// The container-name allowlist Topgrade is permitted to `exec` into.
+    /// Required, like `vagrant_directories`, so enabling the containers step
+    /// never reaches for a container the user didn't list.
+    pub fn container_names(&self) -> Option<&Vec<String>> {
+        self.config_file.containers.as_ref()?.names.as_ref()
+    }
+
+    /// Whether to start (and later stop again) a stopped listed container,
+    /// or unpause (and later pause again) a paused one, analogous to
+    /// `vagrant_power_on`.
+    pub fn container_start(&self) -> Option<bool> {
+        self.config_file.containers.as_ref()?.start
+    }
+
+    /// The container engine binary to shell out to (`docker` by default).
+    pub fn container_engine(&self) -> Option<&str> {
+        self.config_file.containers.as_ref()?.engine.as_deref()
+    }
+}
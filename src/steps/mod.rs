@@ -0,0 +1,3 @@
+pub mod oci;
+pub mod remote;
+pub mod vagrant;
@@ -0,0 +1,135 @@
+use crate::execution_context::ExecutionContext;
+use crate::executor::CommandExt;
+use crate::steps::remote::{forwarded_flags, run_remote_targets, PowerGuard, PowerState, RemoteRunner, STEP_CONTAINERS};
+use crate::terminal::print_separator;
+use crate::utils;
+use anyhow::Result;
+use log::debug;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Debug, Copy, Clone)]
+enum ContainerStatus {
+    Running,
+    Paused,
+    Stopped,
+}
+
+impl From<ContainerStatus> for PowerState {
+    fn from(status: ContainerStatus) -> Self {
+        match status {
+            ContainerStatus::Running => PowerState::On,
+            ContainerStatus::Paused => PowerState::Suspended,
+            ContainerStatus::Stopped => PowerState::Off,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Container {
+    name: String,
+    status: ContainerStatus,
+}
+
+impl Display for Container {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+struct Oci {
+    engine: PathBuf,
+    names: Vec<String>,
+}
+
+impl Oci {
+    fn list_containers(&self) -> Result<Vec<Container>> {
+        let output = Command::new(&self.engine)
+            .args(&["ps", "-a", "--format", "{{.Names}}\t{{.State}}"])
+            .check_output()?;
+        debug!("{:?} output: {}", self.engine, output);
+
+        let containers = output
+            .lines()
+            .filter_map(|line| {
+                let mut elements = line.split('\t');
+                let name = elements.next()?.to_string();
+                if !self.names.contains(&name) {
+                    return None;
+                }
+                let status = match elements.next()? {
+                    "running" => ContainerStatus::Running,
+                    "paused" => ContainerStatus::Paused,
+                    _ => ContainerStatus::Stopped,
+                };
+                Some(Container { name, status })
+            })
+            .collect();
+
+        Ok(containers)
+    }
+}
+
+impl RemoteRunner for Oci {
+    type Target = Container;
+
+    fn discover(&self) -> Result<Vec<Container>> {
+        self.list_containers()
+    }
+
+    fn power_state(&self, target: &Container) -> Result<PowerState> {
+        Ok(target.status.into())
+    }
+
+    fn ensure_running<'a>(&'a self, target: &'a Container, ctx: &'a ExecutionContext<'a>) -> Result<PowerGuard<'a>> {
+        let (on_subcommand, off_subcommand) = match target.status {
+            ContainerStatus::Stopped => ("start", "stop"),
+            ContainerStatus::Paused => ("unpause", "pause"),
+            ContainerStatus::Running => unreachable!(),
+        };
+
+        println!("Starting {}", target);
+        ctx.run_type()
+            .execute(&self.engine)
+            .args(&[on_subcommand, &target.name])
+            .check_run()?;
+
+        Ok(PowerGuard::new(move || {
+            println!("Stopping {}", target);
+            ctx.run_type()
+                .execute(&self.engine)
+                .args(&[off_subcommand, &target.name])
+                .check_run()
+                .ok();
+        }))
+    }
+
+    fn target_prefix(&self, target: &Container) -> String {
+        target.name.clone()
+    }
+
+    fn run_topgrade(&self, target: &Container, prefix: &str, ctx: &ExecutionContext) -> Result<()> {
+        let mut args = vec!["exec".to_string(), target.name.clone()];
+        args.push("env".to_string());
+        args.push(format!("TOPGRADE_PREFIX={}", prefix));
+        args.push("topgrade".to_string());
+        args.extend(forwarded_flags(ctx, STEP_CONTAINERS));
+
+        ctx.run_type().execute(&self.engine).args(&args).check_run()
+    }
+}
+
+pub fn topgrade_containers(ctx: &ExecutionContext) -> Result<()> {
+    let names = utils::require_option(ctx.config().container_names())?.to_vec();
+    let start = ctx.config().container_start().unwrap_or(false);
+    let engine_name = ctx.config().container_engine().unwrap_or("docker");
+    let oci = Oci {
+        engine: utils::require(engine_name)?,
+        names,
+    };
+
+    print_separator("Containers");
+
+    run_remote_targets(&oci, ctx, start)
+}
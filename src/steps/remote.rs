@@ -0,0 +1,133 @@
+use crate::execution_context::ExecutionContext;
+use anyhow::Result;
+use log::debug;
+use std::fmt::Display;
+
+/// `--only`/`--disable` step name for the Vagrant backend.
+pub const STEP_VAGRANT: &str = "vagrant";
+/// `--only`/`--disable` step name for the container backend.
+pub const STEP_CONTAINERS: &str = "containers";
+
+/// Power state of a remote target, as reported by a `RemoteRunner` backend.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PowerState {
+    On,
+    Off,
+    Suspended,
+}
+
+/// Restores a target's prior power state when dropped, mirroring the
+/// power-on/run/power-off lifecycle Vagrant boxes have always used.
+pub struct PowerGuard<'a> {
+    restore: Option<Box<dyn FnOnce() + 'a>>,
+}
+
+impl<'a> PowerGuard<'a> {
+    pub fn new(restore: impl FnOnce() + 'a) -> Self {
+        Self {
+            restore: Some(Box::new(restore)),
+        }
+    }
+}
+
+impl<'a> Drop for PowerGuard<'a> {
+    fn drop(&mut self) {
+        if let Some(restore) = self.restore.take() {
+            restore();
+        }
+    }
+}
+
+/// A backend capable of discovering remote targets, temporarily powering
+/// them on, and running Topgrade inside them. `Vagrant` is the first
+/// implementation; other backends (SSH hosts, containers, ...) can be
+/// plugged in without touching `run_remote_targets`.
+pub trait RemoteRunner {
+    type Target: Display;
+
+    /// Enumerate the targets this backend currently knows about.
+    fn discover(&self) -> Result<Vec<Self::Target>>;
+
+    /// Query a target's current power state.
+    fn power_state(&self, target: &Self::Target) -> Result<PowerState>;
+
+    /// Power on `target`, which must not already be running (callers check
+    /// `power_state` first), returning a guard that restores its prior power
+    /// state when dropped.
+    fn ensure_running<'a>(&'a self, target: &'a Self::Target, ctx: &'a ExecutionContext<'a>) -> Result<PowerGuard<'a>>;
+
+    /// The `TOPGRADE_PREFIX` to use for a nested invocation inside `target`.
+    fn target_prefix(&self, target: &Self::Target) -> String;
+
+    /// Run Topgrade inside `target`, forwarding the outer invocation's flags.
+    fn run_topgrade(&self, target: &Self::Target, prefix: &str, ctx: &ExecutionContext) -> Result<()>;
+}
+
+/// Power on (if necessary), run Topgrade inside, and power back off every
+/// target a `RemoteRunner` discovers. Targets that are not running are
+/// skipped when `power_on_enabled` is false.
+pub fn run_remote_targets<R: RemoteRunner>(runner: &R, ctx: &ExecutionContext, power_on_enabled: bool) -> Result<()> {
+    for target in runner.discover()? {
+        let mut _power_guard = None;
+        if runner.power_state(&target)? != PowerState::On {
+            if !power_on_enabled {
+                debug!("Skipping powered off target {}", target);
+                continue;
+            }
+            _power_guard = Some(runner.ensure_running(&target, ctx)?);
+        }
+
+        println!("Running Topgrade in {}", target);
+        let prefix = runner.target_prefix(&target);
+        runner.run_topgrade(&target, &prefix, ctx)?;
+    }
+    Ok(())
+}
+
+/// Build the flag list a nested Topgrade invocation needs to behave like
+/// the outer one, so e.g. `topgrade --dry-run` against Vagrant boxes or
+/// containers doesn't actually mutate them.
+///
+/// `current_step` (one of the `STEP_*` constants) is excluded from any
+/// forwarded `--only`/`--disable` list: the outer invocation selected it to
+/// reach this backend in the first place, but the guest/container doesn't
+/// have its own nested boxes/containers to run it against, so forwarding it
+/// verbatim would make the nested run execute zero steps.
+pub fn forwarded_flags(ctx: &ExecutionContext, current_step: &str) -> Vec<String> {
+    let mut flags = Vec::new();
+
+    if ctx.config().dry_run() {
+        flags.push("--dry-run".to_string());
+    }
+    if ctx.config().cleanup() {
+        flags.push("--cleanup".to_string());
+    }
+    if ctx.config().yes() {
+        flags.push("-y".to_string());
+    }
+    for step in ctx.config().disable() {
+        if step.as_str() == current_step {
+            continue;
+        }
+        flags.push("--disable".to_string());
+        flags.push(step.to_string());
+    }
+    for step in ctx.config().only() {
+        if step.as_str() == current_step {
+            continue;
+        }
+        flags.push("--only".to_string());
+        flags.push(step.to_string());
+    }
+    if ctx.config().verbose() {
+        flags.push("-v".to_string());
+    }
+
+    flags
+}
+
+/// Quote `arg` for safe inclusion in a single POSIX shell command string,
+/// such as the one passed to `vagrant ssh -c`.
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
@@ -1,12 +1,16 @@
 use crate::execution_context::ExecutionContext;
 use crate::executor::CommandExt;
+use crate::steps::remote::{
+    forwarded_flags, run_remote_targets, shell_quote, PowerGuard, PowerState, RemoteRunner, STEP_VAGRANT,
+};
 use crate::terminal::print_separator;
 use crate::utils;
 use anyhow::Result;
 use log::debug;
-use std::path::{Path, PathBuf};
+use std::fmt::Display;
+use std::path::PathBuf;
 use std::process::Command;
-use std::{fmt::Display, str::FromStr};
+use std::str::FromStr;
 use strum::EnumString;
 
 #[derive(Debug, Copy, Clone, EnumString)]
@@ -18,22 +22,24 @@ enum BoxStatus {
     Aborted,
 }
 
-impl BoxStatus {
-    fn powered_on(self) -> bool {
-        match self {
-            BoxStatus::Running => true,
-            _ => false,
+impl From<BoxStatus> for PowerState {
+    fn from(status: BoxStatus) -> Self {
+        match status {
+            BoxStatus::Running => PowerState::On,
+            BoxStatus::Saved => PowerState::Suspended,
+            BoxStatus::PowerOff | BoxStatus::Aborted => PowerState::Off,
         }
     }
 }
 
 #[derive(Debug)]
-struct VagrantBox<'a> {
-    path: &'a str,
+pub struct VagrantBox {
+    path: String,
     name: String,
+    status: BoxStatus,
 }
 
-impl<'a> Display for VagrantBox<'a> {
+impl Display for VagrantBox {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} @ {}", self.name, self.path)
     }
@@ -41,10 +47,11 @@ impl<'a> Display for VagrantBox<'a> {
 
 struct Vagrant {
     path: PathBuf,
+    directories: Vec<String>,
 }
 
 impl Vagrant {
-    fn get_boxes<'a>(&self, directory: &'a str) -> Result<Vec<(VagrantBox<'a>, BoxStatus)>> {
+    fn get_boxes(&self, directory: &str) -> Result<Vec<VagrantBox>> {
         let output = Command::new(&self.path)
             .arg("status")
             .current_dir(directory)
@@ -58,124 +65,106 @@ impl Vagrant {
             .map(|line| {
                 debug!("Vagrant line: {:?}", line);
                 let mut elements = line.split_whitespace();
+                let name = elements.next().unwrap().to_string();
+                let status = BoxStatus::from_str(elements.next().unwrap()).unwrap();
                 let vagrant_box = VagrantBox {
-                    name: elements.next().unwrap().to_string(),
-                    path: directory,
+                    name,
+                    path: directory.to_string(),
+                    status,
                 };
-                let box_status = BoxStatus::from_str(elements.next().unwrap()).unwrap();
-                debug!("{:?}: {:?}", vagrant_box, box_status);
-                (vagrant_box, box_status)
+                debug!("{:?}", vagrant_box);
+                vagrant_box
             })
             .collect();
 
         Ok(boxes)
     }
+}
 
-    fn temporary_power_on<'a>(
-        &'a self,
-        vagrant_box: &'a VagrantBox,
-        status: BoxStatus,
-        ctx: &'a ExecutionContext,
-    ) -> Result<TemporaryPowerOn<'a>> {
-        TemporaryPowerOn::create(&self.path, vagrant_box, status, ctx)
+impl RemoteRunner for Vagrant {
+    type Target = VagrantBox;
+
+    fn discover(&self) -> Result<Vec<VagrantBox>> {
+        let mut boxes = Vec::new();
+        for directory in &self.directories {
+            boxes.extend(self.get_boxes(directory)?);
+        }
+        Ok(boxes)
     }
-}
 
-struct TemporaryPowerOn<'a> {
-    vagrant: &'a Path,
-    vagrant_box: &'a VagrantBox<'a>,
-    status: BoxStatus,
-    ctx: &'a ExecutionContext<'a>,
-}
+    fn power_state(&self, target: &VagrantBox) -> Result<PowerState> {
+        Ok(target.status.into())
+    }
 
-impl<'a> TemporaryPowerOn<'a> {
-    fn create(
-        vagrant: &'a Path,
-        vagrant_box: &'a VagrantBox<'a>,
-        status: BoxStatus,
-        ctx: &'a ExecutionContext<'a>,
-    ) -> Result<Self> {
-        let subcommand = match status {
+    fn ensure_running<'a>(&'a self, target: &'a VagrantBox, ctx: &'a ExecutionContext<'a>) -> Result<PowerGuard<'a>> {
+        let on_subcommand = match target.status {
             BoxStatus::PowerOff | BoxStatus::Aborted => "up",
             BoxStatus::Saved => "resume",
             BoxStatus::Running => unreachable!(),
         };
-        println!("Powering on {}", vagrant_box);
+        let off_subcommand = match target.status {
+            BoxStatus::PowerOff | BoxStatus::Aborted => "halt",
+            BoxStatus::Saved => "suspend",
+            BoxStatus::Running => unreachable!(),
+        };
 
+        println!("Powering on {}", target);
         ctx.run_type()
-            .execute(vagrant)
-            .args(&[subcommand, &vagrant_box.name])
-            .current_dir(vagrant_box.path)
+            .execute(&self.path)
+            .args(&[on_subcommand, &target.name])
+            .current_dir(&target.path)
             .check_run()?;
-        Ok(TemporaryPowerOn {
-            vagrant,
-            vagrant_box,
-            status,
-            ctx,
-        })
+
+        Ok(PowerGuard::new(move || {
+            println!("Powering off {}", target);
+            ctx.run_type()
+                .execute(&self.path)
+                .args(&[off_subcommand, &target.name])
+                .current_dir(&target.path)
+                .check_run()
+                .ok();
+        }))
     }
-}
 
-impl<'a> Drop for TemporaryPowerOn<'a> {
-    fn drop(&mut self) {
-        let subcommand = match self.status {
-            BoxStatus::PowerOff | BoxStatus::Aborted => "halt",
-            BoxStatus::Saved => "suspend",
-            BoxStatus::Running => unreachable!(),
-        };
+    fn target_prefix(&self, target: &VagrantBox) -> String {
+        if target.name == "default" {
+            PathBuf::from(&target.path)
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        } else {
+            target.name.clone()
+        }
+    }
 
-        println!("Powering off {}", self.vagrant_box);
-        self.ctx
-            .run_type()
-            .execute(self.vagrant)
-            .args(&[subcommand, &self.vagrant_box.name])
-            .current_dir(self.vagrant_box.path)
+    fn run_topgrade(&self, target: &VagrantBox, prefix: &str, ctx: &ExecutionContext) -> Result<()> {
+        let mut words = vec![
+            "env".to_string(),
+            format!("TOPGRADE_PREFIX={}", prefix),
+            "topgrade".to_string(),
+        ];
+        words.extend(forwarded_flags(ctx, STEP_VAGRANT));
+        let command = words.iter().map(|word| shell_quote(word)).collect::<Vec<_>>().join(" ");
+
+        ctx.run_type()
+            .execute(&self.path)
+            .current_dir(&target.path)
+            .args(&["ssh", "-c", &command])
             .check_run()
-            .ok();
     }
 }
 
 pub fn topgrade_vagrant_boxes(ctx: &ExecutionContext) -> Result<()> {
     let directories = utils::require_option(ctx.config().vagrant_directories())?;
+    let power_on = ctx.config().vagrant_power_on().unwrap_or(true);
     let vagrant = Vagrant {
         path: utils::require("vagrant")?,
+        directories: directories.to_vec(),
     };
 
     print_separator("Vagrant");
 
-    for directory in directories {
-        let boxes = vagrant.get_boxes(directory)?;
-        debug!("{:?}", boxes);
-        for (vagrant_box, status) in boxes {
-            let mut _poweron = None;
-            if !status.powered_on() {
-                if !(ctx.config().vagrant_power_on().unwrap_or(true)) {
-                    debug!("Skipping powered off box {}", vagrant_box);
-                    continue;
-                } else {
-                    _poweron = Some(vagrant.temporary_power_on(&vagrant_box, status, ctx)?);
-                }
-            }
-
-            println!("Running Topgrade in {}", vagrant_box);
-            let pathbuf = PathBuf::from(directory);
-            let prefix = if vagrant_box.name == "default" {
-                pathbuf.file_name().unwrap().to_str().unwrap()
-            } else {
-                &vagrant_box.name
-            };
-
-            let mut command = format!("env TOPGRADE_PREFIX={} topgrade", prefix);
-            if ctx.config().yes() {
-                command.push_str(" -y");
-            }
-
-            ctx.run_type()
-                .execute(&vagrant.path)
-                .current_dir(directory)
-                .args(&["ssh", "-c", &command])
-                .check_run()?;
-        }
-    }
-    Ok(())
-}
\ No newline at end of file
+    run_remote_targets(&vagrant, ctx, power_on)
+}